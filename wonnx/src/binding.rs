@@ -0,0 +1,81 @@
+use crate::utils::OutputTensor;
+use crate::{Session, SessionError};
+use std::collections::HashMap;
+use wgpu::util::DeviceExt;
+
+/// An IoBinding-style handle returned by [`Session::bind`] for streaming/real-time workloads
+/// that call [`Session::run`] with identically-shaped inputs many times in a row. Each input's
+/// GPU buffer is allocated once, by the first [`BoundSession::write_input`] for its name, and
+/// reused after that; outputs are read back through the model's own staging buffers. Nothing is
+/// allocated at bind time: an input's length is fixed by its first `write_input`, and is not
+/// checked against the model's declared input shape.
+pub struct BoundSession<'session, 'env> {
+    session: &'session Session<'env>,
+    input_buffers: HashMap<String, wgpu::Buffer>,
+    input_lengths: HashMap<String, usize>,
+    last_outputs: Option<HashMap<String, OutputTensor>>,
+}
+
+impl<'session, 'env> BoundSession<'session, 'env> {
+    pub(crate) fn new(session: &'session Session<'env>) -> Self {
+        Self {
+            session,
+            input_buffers: HashMap::new(),
+            input_lengths: HashMap::new(),
+            last_outputs: None,
+        }
+    }
+
+    /// Writes `data` into the GPU-resident buffer bound to `name`, allocating that buffer the
+    /// first time `name` is seen. Later calls for the same `name` reuse the buffer with
+    /// `queue.write_buffer` instead of reallocating; if `data.len()` no longer matches the
+    /// length the buffer was allocated with, a [`SessionError::BoundInputLengthMismatch`] is
+    /// returned, since that first length stays fixed for the life of the binding.
+    pub fn write_input(&mut self, name: &str, data: &[f32]) -> Result<(), SessionError> {
+        if let Some(&expected) = self.input_lengths.get(name) {
+            if expected != data.len() {
+                return Err(SessionError::BoundInputLengthMismatch {
+                    name: name.to_string(),
+                    expected,
+                    actual: data.len(),
+                });
+            }
+
+            self.session
+                .gpu_model()
+                .queue()
+                .write_buffer(&self.input_buffers[name], 0, bytemuck::cast_slice(data));
+            return Ok(());
+        }
+
+        let buffer = self
+            .session
+            .gpu_model()
+            .device()
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some(name),
+                contents: bytemuck::cast_slice(data),
+                // COPY_SRC because `GpuModel::infer_bound` copies straight out of this buffer
+                // into the model's own input buffer with `copy_buffer_to_buffer` every run.
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+            });
+
+        self.input_buffers.insert(name.to_string(), buffer);
+        self.input_lengths.insert(name.to_string(), data.len());
+        Ok(())
+    }
+
+    /// Runs inference using the buffers previously written via [`BoundSession::write_input`],
+    /// without re-uploading or reallocating them, and returns the freshly computed outputs.
+    pub async fn run_bound(&mut self) -> Result<&HashMap<String, OutputTensor>, SessionError> {
+        let outputs = self
+            .session
+            .gpu_model
+            .infer_bound(&self.input_buffers)
+            .await?;
+        self.last_outputs = Some(outputs);
+        Ok(self.last_outputs.as_ref().unwrap())
+    }
+}
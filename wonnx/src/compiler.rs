@@ -0,0 +1,205 @@
+use crate::gpu::{CompiledNode, DispatchNode, PassthroughNode};
+use crate::ir::{Node, OutputKind};
+use std::collections::HashMap;
+use thiserror::Error;
+use wgpu::util::DeviceExt;
+
+#[derive(Error, Debug)]
+pub enum CompileError {
+    #[error("operator '{0}' (opset {1}) is not supported")]
+    UnsupportedOp(String, i64),
+
+    #[error("node producing '{0}' has no input to read from")]
+    MissingInput(String),
+}
+
+/// The result of compiling an IR graph: one compute pipeline per node, plus the GPU buffers the
+/// caller writes inputs into and reads outputs from, and how each output buffer should be
+/// decoded on read-back.
+pub(crate) struct CompiledGraph {
+    pub(crate) nodes: Vec<CompiledNode>,
+    pub(crate) input_buffers: HashMap<String, wgpu::Buffer>,
+    pub(crate) output_buffers: HashMap<String, wgpu::Buffer>,
+    pub(crate) staging_buffers: HashMap<String, wgpu::Buffer>,
+    pub(crate) output_kinds: HashMap<String, OutputKind>,
+}
+
+/// Compiles an optimized IR graph into a sequence of GPU compute dispatches. Each supported
+/// operator type maps to a WGSL compute shader template; unsupported operators fail compilation
+/// with [`CompileError::UnsupportedOp`] rather than silently producing wrong results. Nodes whose
+/// [`OutputKind`] is not [`OutputKind::Tensor`] (`ZipMap`, string-typed casts) are not compiled
+/// to a shader at all: they are passed through as a GPU-side copy of their source node's buffer
+/// and decoded on read-back instead.
+///
+/// Every dispatched node gets two buffers: a `STORAGE` buffer its shader writes into, and a
+/// separate `COPY_DST | MAP_READ` staging buffer that result is copied into before read-back —
+/// `wgpu` does not allow `MAP_READ` to be combined with `STORAGE` on the same buffer. A
+/// passthrough gets only a staging buffer, which its source's buffer is copied into with
+/// `copy_buffer_to_buffer`. Buffers are keyed by the node's `output_name` (the ONNX graph edge
+/// name), not its (possibly non-unique) `display_name`, since that is
+/// what downstream nodes' `inputs` and [`crate::SessionConfig::outputs`] both refer to.
+pub(crate) fn compile(
+    ir: &Node,
+    device: &wgpu::Device,
+    onnx_opset_version: i64,
+) -> Result<CompiledGraph, CompileError> {
+    let mut nodes = Vec::new();
+    let mut input_buffers = HashMap::new();
+    let mut output_buffers: HashMap<String, wgpu::Buffer> = HashMap::new();
+    let mut staging_buffers = HashMap::new();
+    let mut output_kinds = HashMap::new();
+    // Real byte length of each graph edge (graph input or a Tensor node's output). A passthrough
+    // node's own `output_byte_len()` cannot be relied on: a `ZipMap`'s output is a map/sequence
+    // type with no tensor shape, so `element_count()` is 1 and its "byte length" is a single f32. Its
+    // staging buffer must instead be sized from its *source* edge's entry here.
+    let mut byte_lens: HashMap<String, usize> = HashMap::new();
+
+    for (name, shape) in ir.graph_inputs() {
+        let byte_len = element_byte_len(shape);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(name),
+            size: byte_len as u64,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        input_buffers.insert(name.clone(), buffer);
+        byte_lens.insert(name.clone(), byte_len);
+    }
+
+    for node in ir.nodes() {
+        output_kinds.insert(node.output_name.clone(), node.output_kind.clone());
+
+        match &node.output_kind {
+            OutputKind::Tensor => {
+                let shader_source = shader_for_op(&node.op_type, onnx_opset_version).ok_or_else(
+                    || CompileError::UnsupportedOp(node.op_type.clone(), onnx_opset_version),
+                )?;
+
+                let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                    label: Some(&node.display_name),
+                    source: wgpu::ShaderSource::Wgsl(shader_source.into()),
+                });
+
+                let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(&node.display_name),
+                    layout: None,
+                    module: &shader,
+                    entry_point: "main",
+                });
+
+                let input_buffers_for_node = node
+                    .inputs
+                    .iter()
+                    .map(|input| {
+                        input_buffers
+                            .get(input)
+                            .or_else(|| output_buffers.get(input))
+                            .ok_or_else(|| CompileError::MissingInput(node.output_name.clone()))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let output_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&format!("{}_output", node.output_name)),
+                    contents: &vec![0u8; node.output_byte_len()],
+                    usage: wgpu::BufferUsages::STORAGE
+                        | wgpu::BufferUsages::COPY_SRC
+                        | wgpu::BufferUsages::COPY_DST,
+                });
+
+                let bind_group_layout = pipeline.get_bind_group_layout(0);
+                let mut entries: Vec<wgpu::BindGroupEntry> = input_buffers_for_node
+                    .iter()
+                    .enumerate()
+                    .map(|(i, buffer)| wgpu::BindGroupEntry {
+                        binding: i as u32,
+                        resource: buffer.as_entire_binding(),
+                    })
+                    .collect();
+                entries.push(wgpu::BindGroupEntry {
+                    binding: entries.len() as u32,
+                    resource: output_buffer.as_entire_binding(),
+                });
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some(&node.display_name),
+                    layout: &bind_group_layout,
+                    entries: &entries,
+                });
+
+                output_buffers.insert(node.output_name.clone(), output_buffer);
+                byte_lens.insert(node.output_name.clone(), node.output_byte_len());
+
+                let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(&format!("{}_staging", node.output_name)),
+                    size: node.output_byte_len() as u64,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                });
+                staging_buffers.insert(node.output_name.clone(), staging_buffer);
+
+                nodes.push(CompiledNode::Dispatch(DispatchNode {
+                    name: node.output_name.clone(),
+                    display_name: node.display_name.clone(),
+                    op_type: node.op_type.clone(),
+                    output_shape: node.output_shape.clone(),
+                    pipeline,
+                    bind_group,
+                    workgroups: (node.element_count().div_ceil(256).max(1) as u32, 1, 1),
+                }));
+            }
+            OutputKind::ClassifierMap(_) | OutputKind::Strings => {
+                let source = node
+                    .inputs
+                    .first()
+                    .cloned()
+                    .ok_or_else(|| CompileError::MissingInput(node.output_name.clone()))?;
+
+                // Size the staging buffer (and thus the `copy_buffer_to_buffer` length used at
+                // dispatch time) from the *source* edge's real byte length, not this node's own
+                // shapeless `output_byte_len()` — see the `byte_lens` comment above.
+                let source_byte_len = byte_lens
+                    .get(&source)
+                    .copied()
+                    .ok_or_else(|| CompileError::MissingInput(node.output_name.clone()))?;
+                byte_lens.insert(node.output_name.clone(), source_byte_len);
+
+                let staging_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some(&format!("{}_staging", node.output_name)),
+                    size: source_byte_len as u64,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                });
+                staging_buffers.insert(node.output_name.clone(), staging_buffer);
+
+                nodes.push(CompiledNode::Passthrough(PassthroughNode {
+                    name: node.output_name.clone(),
+                    display_name: node.display_name.clone(),
+                    op_type: node.op_type.clone(),
+                    output_shape: node.output_shape.clone(),
+                    source,
+                }));
+            }
+        }
+    }
+
+    Ok(CompiledGraph {
+        nodes,
+        input_buffers,
+        output_buffers,
+        staging_buffers,
+        output_kinds,
+    })
+}
+
+fn element_byte_len(shape: &[i64]) -> usize {
+    shape.iter().product::<i64>().max(0) as usize * std::mem::size_of::<f32>()
+}
+
+/// Looks up the WGSL compute shader template for a given op type, if supported.
+fn shader_for_op(op_type: &str, _onnx_opset_version: i64) -> Option<&'static str> {
+    match op_type {
+        "Relu" => Some(include_str!("../shaders/relu.wgsl")),
+        "Add" => Some(include_str!("../shaders/add.wgsl")),
+        "Identity" => Some(include_str!("../shaders/identity.wgsl")),
+        _ => None,
+    }
+}
@@ -0,0 +1,65 @@
+use crate::resource::{self, DeviceConfig, ResourceError};
+
+/// A [`WonnxEnvironment`] owns the GPU adapter, device and queue that one or more
+/// [Session](crate::Session)s run on. Creating it is the expensive part of getting onto the GPU
+/// (adapter enumeration, device request); sharing one environment across several sessions avoids
+/// paying that cost per model and is a precondition for any buffer or pipeline caching across
+/// sessions. The adapter is kept (not just the device/queue) so callers can inspect e.g.
+/// [`wgpu::Adapter::get_info`] or [`wgpu::Adapter::features`] for the adapter a session actually
+/// ended up running on.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```ignore
+/// let env = WonnxEnvironment::new().await;
+/// let session = Session::from_path_in(&env, "path/to/model.onnx", &SessionConfig::new())
+///     .await
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct WonnxEnvironment {
+    pub(crate) device: wgpu::Device,
+    pub(crate) queue: wgpu::Queue,
+    pub(crate) adapter: wgpu::Adapter,
+}
+
+impl WonnxEnvironment {
+    /// Creates a new [`WonnxEnvironment`], requesting a default GPU adapter, device and queue.
+    pub async fn new() -> Self {
+        let (device, queue, adapter) = resource::request_device_queue().await;
+        Self {
+            device,
+            queue,
+            adapter,
+        }
+    }
+
+    /// Creates a new [`WonnxEnvironment`] on the adapter selected by `device_config`, e.g. to
+    /// pick a specific GPU on a multi-GPU machine or to prefer low power over performance.
+    pub async fn new_with_config(device_config: DeviceConfig) -> Result<Self, ResourceError> {
+        let (device, queue, adapter) =
+            resource::request_device_queue_with_config(&device_config).await?;
+        Ok(Self {
+            device,
+            queue,
+            adapter,
+        })
+    }
+
+    /// The [`wgpu::Device`] backing this environment.
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    /// The [`wgpu::Queue`] backing this environment.
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    /// The [`wgpu::Adapter`] this environment's device and queue were requested from.
+    pub fn adapter(&self) -> &wgpu::Adapter {
+        &self.adapter
+    }
+}
@@ -0,0 +1,384 @@
+use crate::compiler::{self, CompileError};
+use crate::ir::{Node, OutputKind};
+use crate::utils::{DataTypeError, InputTensor, OutputTensor};
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum GpuError {
+    #[error("error compiling the graph to GPU shaders: {0}")]
+    CompileError(#[from] CompileError),
+
+    #[error("error converting tensor data types: {0}")]
+    TypeError(#[from] DataTypeError),
+
+    #[error("invalid input name '{0}'; inspect the file with e.g. Netron to find the correct name")]
+    InvalidInput(String),
+
+    #[error("error mapping a GPU buffer: {0}")]
+    BufferMapError(#[from] wgpu::BufferAsyncError),
+
+    #[error("'{0}' produced a string tensor with invalid UTF-8 data")]
+    InvalidStringOutput(String),
+
+    #[error("'{0}' is a passthrough node (e.g. ZipMap) but its source buffer '{1}' does not exist")]
+    MissingPassthroughSource(String, String),
+}
+
+/// The measured GPU time spent in one compiled node, as recorded by
+/// [`GpuModel::infer_with_profiling`].
+#[derive(Debug, Clone)]
+pub struct NodeProfile {
+    pub node_name: String,
+    pub op_type: String,
+    pub output_shape: Vec<i64>,
+    /// `None` when the adapter does not support [`wgpu::Features::TIMESTAMP_QUERY`], and for
+    /// nodes (like `ZipMap`) that are resolved on read-back rather than dispatched to the GPU.
+    pub gpu_time_ns: Option<u64>,
+}
+
+/// Per-node GPU timing for one call to [`GpuModel::infer_with_profiling`], in execution order.
+pub type InferenceProfile = Vec<NodeProfile>;
+
+/// A node that dispatches an actual GPU compute pass.
+pub(crate) struct DispatchNode {
+    pub(crate) name: String,
+    pub(crate) display_name: String,
+    pub(crate) op_type: String,
+    pub(crate) output_shape: Vec<i64>,
+    pub(crate) pipeline: wgpu::ComputePipeline,
+    pub(crate) bind_group: wgpu::BindGroup,
+    pub(crate) workgroups: (u32, u32, u32),
+}
+
+/// A node that does not run on the GPU at all (e.g. `ZipMap`, a string-producing `Cast`): its
+/// "output" is simply a GPU-side copy of `source`'s compute buffer, relabeled according to its
+/// [`OutputKind`] in [`GpuModel`]'s `output_kinds` when it is read back.
+pub(crate) struct PassthroughNode {
+    pub(crate) name: String,
+    pub(crate) display_name: String,
+    pub(crate) op_type: String,
+    pub(crate) output_shape: Vec<i64>,
+    pub(crate) source: String,
+}
+
+/// One compiled graph node: either a real GPU dispatch, or a passthrough resolved on read-back.
+pub(crate) enum CompiledNode {
+    Dispatch(DispatchNode),
+    Passthrough(PassthroughNode),
+}
+
+impl CompiledNode {
+    fn name(&self) -> &str {
+        match self {
+            CompiledNode::Dispatch(n) => &n.name,
+            CompiledNode::Passthrough(n) => &n.name,
+        }
+    }
+
+    fn display_name(&self) -> &str {
+        match self {
+            CompiledNode::Dispatch(n) => &n.display_name,
+            CompiledNode::Passthrough(n) => &n.display_name,
+        }
+    }
+
+    fn op_type(&self) -> &str {
+        match self {
+            CompiledNode::Dispatch(n) => &n.op_type,
+            CompiledNode::Passthrough(n) => &n.op_type,
+        }
+    }
+
+    fn output_shape(&self) -> &[i64] {
+        match self {
+            CompiledNode::Dispatch(n) => &n.output_shape,
+            CompiledNode::Passthrough(n) => &n.output_shape,
+        }
+    }
+}
+
+/// A model that has been compiled to a sequence of GPU compute dispatches, ready to run
+/// inference against a shared [`wgpu::Device`]/[`wgpu::Queue`].
+pub struct GpuModel {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    nodes: Vec<CompiledNode>,
+    input_buffers: HashMap<String, wgpu::Buffer>,
+    output_buffers: HashMap<String, wgpu::Buffer>,
+    staging_buffers: HashMap<String, wgpu::Buffer>,
+    output_kinds: HashMap<String, OutputKind>,
+}
+
+impl GpuModel {
+    pub fn from(
+        ir: Node,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        onnx_opset_version: i64,
+    ) -> Result<Self, GpuError> {
+        let compiled = compiler::compile(&ir, &device, onnx_opset_version)?;
+        Ok(Self {
+            device,
+            queue,
+            nodes: compiled.nodes,
+            input_buffers: compiled.input_buffers,
+            output_buffers: compiled.output_buffers,
+            staging_buffers: compiled.staging_buffers,
+            output_kinds: compiled.output_kinds,
+        })
+    }
+
+    pub(crate) fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    pub(crate) fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    fn write_inputs(&self, inputs: &HashMap<String, InputTensor<'_>>) -> Result<(), GpuError> {
+        for (name, buffer) in &self.input_buffers {
+            let tensor = inputs
+                .get(name)
+                .ok_or_else(|| GpuError::InvalidInput(name.clone()))?;
+            self.queue
+                .write_buffer(buffer, 0, bytemuck::cast_slice(tensor.as_slice()?));
+        }
+        Ok(())
+    }
+
+    /// Dispatches `node`'s compute pass. Only meaningful for [`CompiledNode::Dispatch`]; a
+    /// passthrough never dispatches, so this is a no-op for it. Split out from
+    /// [`GpuModel::copy_node_to_staging`] so [`GpuModel::infer_with_profiling`] can bracket
+    /// timestamp queries around the dispatch alone, without the staging copy's cost included.
+    fn dispatch_node(&self, encoder: &mut wgpu::CommandEncoder, node: &CompiledNode) {
+        if let CompiledNode::Dispatch(node) = node {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None });
+            pass.set_pipeline(&node.pipeline);
+            pass.set_bind_group(0, &node.bind_group, &[]);
+            pass.dispatch_workgroups(node.workgroups.0, node.workgroups.1, node.workgroups.2);
+        }
+    }
+
+    /// Copies a single node's result from its compute buffer (or, for a passthrough, its
+    /// source's compute buffer) into its own staging buffer so it can be mapped for read-back
+    /// afterwards. Must run after [`GpuModel::dispatch_node`] for the same node.
+    fn copy_node_to_staging(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        node: &CompiledNode,
+    ) -> Result<(), GpuError> {
+        let staging_buffer = &self.staging_buffers[node.name()];
+
+        let source_buffer = match node {
+            CompiledNode::Dispatch(node) => &self.output_buffers[&node.name],
+            CompiledNode::Passthrough(node) => {
+                // `source` is a graph edge name, so it may name either a preceding node's output
+                // or (e.g. a `ZipMap` fed straight from a graph input) a graph input itself.
+                self.output_buffers
+                    .get(&node.source)
+                    .or_else(|| self.input_buffers.get(&node.source))
+                    .ok_or_else(|| {
+                        GpuError::MissingPassthroughSource(node.name.clone(), node.source.clone())
+                    })?
+            }
+        };
+        encoder.copy_buffer_to_buffer(source_buffer, 0, staging_buffer, 0, staging_buffer.size());
+        Ok(())
+    }
+
+    fn record_nodes(&self, encoder: &mut wgpu::CommandEncoder) -> Result<(), GpuError> {
+        for node in &self.nodes {
+            self.dispatch_node(encoder, node);
+            self.copy_node_to_staging(encoder, node)?;
+        }
+        Ok(())
+    }
+
+    async fn read_outputs(&self) -> Result<HashMap<String, OutputTensor>, GpuError> {
+        let mut outputs = HashMap::with_capacity(self.staging_buffers.len());
+        for (name, buffer) in &self.staging_buffers {
+            let slice = buffer.slice(..);
+            let (sender, receiver) = futures::channel::oneshot::channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            self.device.poll(wgpu::Maintain::Wait);
+            receiver.await.unwrap()?;
+            let data = slice.get_mapped_range();
+            let tensor = self.decode_output(name, &data)?;
+            drop(data);
+            buffer.unmap();
+            outputs.insert(name.clone(), tensor);
+        }
+        Ok(outputs)
+    }
+
+    /// Decodes the raw bytes of `name`'s staging buffer according to its [`OutputKind`]: a plain
+    /// dense tensor, a `ZipMap` classifier map (pairing the preceding tensor's floats with the
+    /// node's class labels), or a string tensor.
+    ///
+    /// String tensors are not produced by any WGSL shader (`wgpu` buffers are just bytes); the
+    /// only way a [`OutputKind::Strings`] node currently arises is a `Cast` to string, which is
+    /// compiled as a passthrough. Its buffer therefore still holds its *source*'s `f32` data, so
+    /// decoding formats each element with Rust's default `f32` `Display` rather than
+    /// reinterpreting the bytes as text.
+    fn decode_output(&self, name: &str, data: &[u8]) -> Result<OutputTensor, GpuError> {
+        match self.output_kinds.get(name) {
+            Some(OutputKind::ClassifierMap(keys)) => {
+                let floats: &[f32] = bytemuck::cast_slice(data);
+                let entries = keys
+                    .iter()
+                    .cloned()
+                    .zip(floats.iter().copied())
+                    .collect::<Vec<_>>();
+                Ok(OutputTensor::Map(entries))
+            }
+            Some(OutputKind::Strings) => {
+                let floats: &[f32] = bytemuck::cast_slice(data);
+                Ok(OutputTensor::Strings(
+                    floats.iter().map(|value| value.to_string()).collect(),
+                ))
+            }
+            _ => Ok(OutputTensor::F32(bytemuck::cast_slice(data).to_vec())),
+        }
+    }
+
+    /// Runs the compiled graph against `inputs`, dispatching one compute pass per node.
+    pub async fn infer(
+        &self,
+        inputs: &HashMap<String, InputTensor<'_>>,
+    ) -> Result<HashMap<String, OutputTensor>, GpuError> {
+        self.write_inputs(inputs)?;
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        self.record_nodes(&mut encoder)?;
+        self.queue.submit(Some(encoder.finish()));
+
+        self.read_outputs().await
+    }
+
+    /// Runs the compiled graph using inputs that are already resident on the GPU (see
+    /// [`crate::BoundSession`]), copying them into this model's input buffers with a GPU-side
+    /// `copy_buffer_to_buffer` rather than a CPU upload, then dispatching exactly as
+    /// [`GpuModel::infer`] does.
+    pub(crate) async fn infer_bound(
+        &self,
+        bound_input_buffers: &HashMap<String, wgpu::Buffer>,
+    ) -> Result<HashMap<String, OutputTensor>, GpuError> {
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        for (name, bound_buffer) in bound_input_buffers {
+            let input_buffer = self
+                .input_buffers
+                .get(name)
+                .ok_or_else(|| GpuError::InvalidInput(name.clone()))?;
+            encoder.copy_buffer_to_buffer(bound_buffer, 0, input_buffer, 0, bound_buffer.size());
+        }
+
+        self.record_nodes(&mut encoder)?;
+        self.queue.submit(Some(encoder.finish()));
+
+        self.read_outputs().await
+    }
+
+    /// Like [`GpuModel::infer`], but additionally measures the GPU wall-clock time each
+    /// dispatched node took, by bracketing its compute pass with timestamp queries. Passthrough
+    /// nodes (`ZipMap`, string casts) always report `None`, since they never dispatch. Falls
+    /// back to reporting `None` for every node when the adapter lacks
+    /// [`wgpu::Features::TIMESTAMP_QUERY`].
+    pub async fn infer_with_profiling(
+        &self,
+        inputs: &HashMap<String, InputTensor<'_>>,
+    ) -> Result<(HashMap<String, OutputTensor>, InferenceProfile), GpuError> {
+        self.write_inputs(inputs)?;
+
+        let supports_timestamps = self
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY);
+
+        let query_set = supports_timestamps.then(|| {
+            self.device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("wonnx-profiling"),
+                ty: wgpu::QueryType::Timestamp,
+                count: self.nodes.len() as u32 * 2,
+            })
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(query_set) = &query_set {
+                encoder.write_timestamp(query_set, i as u32 * 2);
+            }
+            self.dispatch_node(&mut encoder, node);
+            if let Some(query_set) = &query_set {
+                encoder.write_timestamp(query_set, i as u32 * 2 + 1);
+            }
+            self.copy_node_to_staging(&mut encoder, node)?;
+        }
+
+        let timings = if let Some(query_set) = &query_set {
+            let resolve_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("wonnx-profiling-resolve"),
+                size: self.nodes.len() as u64 * 2 * std::mem::size_of::<u64>() as u64,
+                usage: wgpu::BufferUsages::QUERY_RESOLVE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::MAP_READ,
+                mapped_at_creation: false,
+            });
+            encoder.resolve_query_set(query_set, 0..self.nodes.len() as u32 * 2, &resolve_buffer, 0);
+
+            self.queue.submit(Some(encoder.finish()));
+
+            let slice = resolve_buffer.slice(..);
+            let (sender, receiver) = futures::channel::oneshot::channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            self.device.poll(wgpu::Maintain::Wait);
+            receiver.await.unwrap()?;
+            let raw: Vec<u64> = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+            resolve_buffer.unmap();
+
+            let period = self.queue.get_timestamp_period() as f64;
+            self.nodes
+                .iter()
+                .zip(raw.chunks_exact(2))
+                .map(|(node, pair)| match node {
+                    CompiledNode::Dispatch(_) => {
+                        Some(((pair[1] - pair[0]) as f64 * period) as u64)
+                    }
+                    CompiledNode::Passthrough(_) => None,
+                })
+                .collect::<Vec<_>>()
+        } else {
+            self.queue.submit(Some(encoder.finish()));
+            vec![None; self.nodes.len()]
+        };
+
+        let outputs = self.read_outputs().await?;
+
+        let profile = self
+            .nodes
+            .iter()
+            .zip(timings)
+            .map(|(node, gpu_time_ns)| NodeProfile {
+                node_name: node.display_name().to_string(),
+                op_type: node.op_type().to_string(),
+                output_shape: node.output_shape().to_vec(),
+                gpu_time_ns,
+            })
+            .collect();
+
+        Ok((outputs, profile))
+    }
+}
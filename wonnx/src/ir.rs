@@ -0,0 +1,225 @@
+use crate::onnx::{GraphProto, ModelProto, NodeProto};
+use crate::utils::MapKey;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum IrError {
+    #[error("input '{0}' is used but never produced by any node or declared as a graph input")]
+    UnresolvedInput(String),
+
+    #[error("requested output '{0}' does not exist in the graph")]
+    UnknownOutput(String),
+
+    #[error(
+        "could not determine the shape of output '{0}'; it is not declared in the graph's inputs, outputs or value_info"
+    )]
+    MissingShapeInfo(String),
+}
+
+/// What kind of value a node's output buffer actually holds, once decoded. Most nodes produce a
+/// plain numeric tensor; `ZipMap` instead relabels a preceding tensor's columns into a
+/// classifier map, and some nodes (e.g. a string-typed `Cast`) produce string tensors. Neither
+/// of these runs on the GPU: they are resolved on read-back, by
+/// [`crate::gpu::GpuModel::infer`].
+#[derive(Debug, Clone)]
+pub enum OutputKind {
+    Tensor,
+    ClassifierMap(Vec<MapKey>),
+    Strings,
+}
+
+/// A single node in the optimizer/compiler's intermediate representation.
+///
+/// `output_name` (not `display_name`) is the node's identity as far as buffers and the
+/// `HashMap<String, OutputTensor>` returned from `run` are concerned: it is the ONNX graph edge
+/// name the node's output is known by, which is what [`inputs`](NodeDefinition::inputs) of
+/// downstream nodes and [`SessionConfig::outputs`](crate::SessionConfig::outputs) both refer to.
+/// `display_name` (`NodeProto.name`, which ONNX allows to be empty or to collide with an edge
+/// name) is only used for human-facing labels such as shader labels and [`crate::NodeProfile`].
+#[derive(Debug, Clone)]
+pub struct NodeDefinition {
+    pub display_name: String,
+    pub op_type: String,
+    pub inputs: Vec<String>,
+    pub output_name: String,
+    pub output_shape: Vec<i64>,
+    pub output_kind: OutputKind,
+}
+
+impl NodeDefinition {
+    /// The number of elements in this node's output tensor.
+    pub fn element_count(&self) -> usize {
+        self.output_shape.iter().product::<i64>().max(0) as usize
+    }
+
+    /// The size in bytes of this node's output tensor, assuming 32-bit elements.
+    pub fn output_byte_len(&self) -> usize {
+        self.element_count() * std::mem::size_of::<f32>()
+    }
+}
+
+/// The root of a graph's intermediate representation: its nodes in topological (execution)
+/// order, plus the shapes of the graph's own inputs (which are not produced by any node and so
+/// need their buffers allocated up front).
+#[derive(Debug, Clone)]
+pub struct Node {
+    nodes: Vec<NodeDefinition>,
+    graph_inputs: Vec<(String, Vec<i64>)>,
+}
+
+impl Node {
+    /// Builds the IR for `model`, restricting the computed outputs to `outputs` when given
+    /// (`None` keeps every graph output).
+    pub fn from_model(model: &ModelProto, outputs: Option<&[String]>) -> Result<Self, IrError> {
+        let graph = model.get_graph();
+        let wanted: Option<Vec<String>> = outputs.map(|o| o.to_vec());
+        let shapes = shape_map(graph);
+
+        let producer_names: std::collections::HashSet<&str> = graph
+            .get_node()
+            .iter()
+            .map(|n| n.get_output()[0].as_str())
+            .collect();
+
+        let mut nodes = Vec::with_capacity(graph.get_node().len());
+        for n in graph.get_node() {
+            let output_name = n.get_output()[0].to_string();
+            let display_name = if n.get_name().is_empty() {
+                format!("{}_{}", n.get_op_type(), output_name)
+            } else {
+                n.get_name().to_string()
+            };
+
+            let output_shape = shapes
+                .get(&output_name)
+                .cloned()
+                .ok_or_else(|| IrError::MissingShapeInfo(output_name.clone()))?;
+
+            for input in n.get_input() {
+                if !producer_names.contains(input.as_str()) && !shapes.contains_key(input) {
+                    return Err(IrError::UnresolvedInput(input.clone()));
+                }
+            }
+
+            nodes.push(NodeDefinition {
+                display_name,
+                op_type: n.get_op_type().to_string(),
+                inputs: n.get_input().to_vec(),
+                output_kind: output_kind_of(n),
+                output_shape,
+                output_name,
+            });
+        }
+
+        if let Some(wanted) = &wanted {
+            let available: std::collections::HashSet<&str> =
+                nodes.iter().map(|n| n.output_name.as_str()).collect();
+            for name in wanted {
+                if !available.contains(name.as_str()) {
+                    return Err(IrError::UnknownOutput(name.clone()));
+                }
+            }
+            // Keep the requested nodes plus everything they transitively read from; `nodes` is
+            // in topological order, so one backwards walk collects every dependency.
+            let mut needed: std::collections::HashSet<String> = wanted.iter().cloned().collect();
+            for n in nodes.iter().rev() {
+                if needed.contains(&n.output_name) {
+                    needed.extend(n.inputs.iter().cloned());
+                }
+            }
+            nodes.retain(|n| needed.contains(&n.output_name));
+        }
+
+        let graph_inputs = graph
+            .get_input()
+            .iter()
+            .map(|input| {
+                let shape = shapes.get(input.get_name()).cloned().unwrap_or_default();
+                (input.get_name().to_string(), shape)
+            })
+            .collect();
+
+        Ok(Self {
+            nodes,
+            graph_inputs,
+        })
+    }
+
+    /// This graph's nodes, in execution order.
+    pub fn nodes(&self) -> &[NodeDefinition] {
+        &self.nodes
+    }
+
+    /// The graph's own inputs (name, shape), which are not produced by any node.
+    pub fn graph_inputs(&self) -> &[(String, Vec<i64>)] {
+        &self.graph_inputs
+    }
+}
+
+/// Collects the declared shape of every tensor name the graph mentions in its `input`, `output`
+/// or `value_info` lists, keyed by tensor name. Nodes whose output is not declared in any of
+/// these (e.g. a model missing intermediate `value_info`) cannot be sized and fail IR
+/// construction with [`IrError::MissingShapeInfo`] rather than silently being compiled with a
+/// bogus shape.
+fn shape_map(graph: &GraphProto) -> HashMap<String, Vec<i64>> {
+    graph
+        .get_input()
+        .iter()
+        .chain(graph.get_output())
+        .chain(graph.get_value_info())
+        .map(|value_info| {
+            let dims = value_info
+                .get_field_type()
+                .get_tensor_type()
+                .get_shape()
+                .get_dim()
+                .iter()
+                .map(|dim| dim.get_dim_value())
+                .collect();
+            (value_info.get_name().to_string(), dims)
+        })
+        .collect()
+}
+
+/// Figures out whether `n`'s output should be read back as a plain tensor, a `ZipMap`
+/// classifier map, or a string tensor, based on its op type and (for `ZipMap`) its
+/// `classlabels_int64s`/`classlabels_strings` attributes.
+fn output_kind_of(n: &NodeProto) -> OutputKind {
+    match n.get_op_type() {
+        "ZipMap" => {
+            let keys = n
+                .get_attribute()
+                .iter()
+                .find_map(|attr| match attr.get_name() {
+                    "classlabels_int64s" => Some(
+                        attr.get_ints()
+                            .iter()
+                            .map(|&v| MapKey::Int64(v))
+                            .collect::<Vec<_>>(),
+                    ),
+                    "classlabels_strings" => Some(
+                        attr.get_strings()
+                            .iter()
+                            .map(|s| MapKey::String(String::from_utf8_lossy(s).into_owned()))
+                            .collect::<Vec<_>>(),
+                    ),
+                    _ => None,
+                })
+                .unwrap_or_default();
+            OutputKind::ClassifierMap(keys)
+        }
+        "Cast" => {
+            let casts_to_string = n.get_attribute().iter().any(|attr| {
+                attr.get_name() == "to"
+                    && attr.get_i() == crate::onnx::TensorProto_DataType::STRING as i64
+            });
+            if casts_to_string {
+                OutputKind::Strings
+            } else {
+                OutputKind::Tensor
+            }
+        }
+        _ => OutputKind::Tensor,
+    }
+}
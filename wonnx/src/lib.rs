@@ -1,8 +1,11 @@
+mod binding;
 mod compiler;
+mod environment;
 mod gpu;
 mod ir;
 pub mod onnx;
 mod optimizer;
+mod registry;
 mod resource;
 pub mod utils;
 
@@ -13,13 +16,20 @@ use compiler::CompileError;
 use gpu::GpuError;
 use ir::IrError;
 use optimizer::{Optimizer, OptimizerError};
+pub use optimizer::OptimizationLevel;
 use protobuf::{self, Message, ProtobufError};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::Path;
 use std::result::Result;
 use utils::{DataTypeError, InputTensor, OutputTensor};
 
+pub use crate::binding::BoundSession;
+pub use crate::environment::WonnxEnvironment;
 use crate::gpu::GpuModel;
+pub use crate::gpu::{InferenceProfile, NodeProfile};
+pub use crate::registry::{ModelRegistry, RegistryError};
+pub use crate::resource::DeviceConfig;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -46,7 +56,14 @@ pub enum WonnxError {
 /// ```ignore
 /// let mut session = Session::from_path("path/to/model.onnx").await.unwrap();
 /// ```
-pub struct Session {
+///
+/// A [Session] borrows its [`WonnxEnvironment`] (device, queue) when created with
+/// [`Session::from_model_in`] or [`Session::from_path_in`], so several sessions can share one
+/// GPU context; the `'env` lifetime ties the session to the environment it borrowed. The
+/// [`Session::from_model`]/[`Session::from_path`] convenience constructors instead spin up and
+/// own a private environment, yielding a `Session<'static>`.
+pub struct Session<'env> {
+    environment: Cow<'env, WonnxEnvironment>,
     gpu_model: GpuModel,
 }
 
@@ -85,6 +102,18 @@ pub enum SessionError {
 
     #[error("optimizer error: {0}")]
     OptimizerError(#[from] OptimizerError),
+
+    #[error("error selecting a GPU device: {0}")]
+    ResourceError(#[from] resource::ResourceError),
+
+    #[error(
+        "bound input '{name}' was allocated with length {expected} but a later write_input call passed length {actual}"
+    )]
+    BoundInputLengthMismatch {
+        name: String,
+        expected: usize,
+        actual: usize,
+    },
 }
 
 /// Provides optional configuration when creating an inference [Session].
@@ -92,12 +121,25 @@ pub enum SessionError {
 pub struct SessionConfig {
     /// When set, only the specified outputs will be calculated, and nodes that are not inputs to these outputs may not be processed
     pub outputs: Option<Vec<String>>,
+
+    /// Controls which graph-optimization passes are run while compiling the model. Defaults to
+    /// [`OptimizationLevel::All`]. Lowering this can help when a model produces incorrect
+    /// results and you want to rule out a miscompiling optimization pass, or when the same
+    /// model is compiled repeatedly and the extra passes are not worth their cost.
+    ///
+    /// No pass is implemented yet (see [`OptimizationLevel`]), so every level currently compiles
+    /// to the exact same result; this field has no observable effect on compiled output until
+    /// at least one pass lands.
+    pub optimization_level: OptimizationLevel,
 }
 
 impl SessionConfig {
     /// Creates a new [SessionConfig] struct with the default options set.
     pub fn new() -> Self {
-        Self { outputs: None }
+        Self {
+            outputs: None,
+            optimization_level: OptimizationLevel::default(),
+        }
     }
 
     /// Sets [`SessionConfig::outputs`] to the specified value and returns [Self].
@@ -105,6 +147,13 @@ impl SessionConfig {
         self.outputs = outputs;
         self
     }
+
+    /// Sets [`SessionConfig::optimization_level`] to the specified value and returns [Self].
+    /// Has no observable effect yet; see [`SessionConfig::optimization_level`].
+    pub fn with_optimization_level(mut self, optimization_level: OptimizationLevel) -> Self {
+        self.optimization_level = optimization_level;
+        self
+    }
 }
 
 impl Default for SessionConfig {
@@ -113,9 +162,9 @@ impl Default for SessionConfig {
     }
 }
 
-impl Session {
+impl Session<'static> {
     // Read an ONNX model from a path and create a session, using default [session config](SessionConfig).
-    pub async fn from_path<P: AsRef<Path>>(path: P) -> Result<Session, SessionError> {
+    pub async fn from_path<P: AsRef<Path>>(path: P) -> Result<Session<'static>, SessionError> {
         let model = onnx::ModelProto::parse_from_bytes(&std::fs::read(path)?)?;
         Session::from_model(model).await
     }
@@ -124,13 +173,13 @@ impl Session {
     pub async fn from_path_with_config<P: AsRef<Path>>(
         path: P,
         config: &SessionConfig,
-    ) -> Result<Session, SessionError> {
+    ) -> Result<Session<'static>, SessionError> {
         let model = onnx::ModelProto::parse_from_bytes(&std::fs::read(path)?)?;
         Session::from_model_with_config(model, config).await
     }
 
     /// Read an ONNX model from bytes and create a session, using default [session config](SessionConfig).
-    pub async fn from_bytes(bytes: &[u8]) -> Result<Session, SessionError> {
+    pub async fn from_bytes(bytes: &[u8]) -> Result<Session<'static>, SessionError> {
         let model = onnx::ModelProto::parse_from_bytes(bytes)?;
         Session::from_model(model).await
     }
@@ -139,18 +188,84 @@ impl Session {
     pub async fn from_bytes_with_config(
         bytes: &[u8],
         config: &SessionConfig,
-    ) -> Result<Session, SessionError> {
+    ) -> Result<Session<'static>, SessionError> {
         let model = onnx::ModelProto::parse_from_bytes(bytes)?;
         Session::from_model_with_config(model, config).await
     }
 
-    /// Create a session using the provided [`onnx::ModelProto`] and [session config](SessionConfig).
+    /// Create a session using the provided [`onnx::ModelProto`] and [session config](SessionConfig),
+    /// spinning up a private [`WonnxEnvironment`] owned by the session. Use
+    /// [`Session::from_model_in`] instead to share a GPU environment across several sessions.
     pub async fn from_model_with_config(
         model: onnx::ModelProto,
         config: &SessionConfig,
-    ) -> Result<Session, SessionError> {
-        let (device, queue) = resource::request_device_queue().await;
+    ) -> Result<Session<'static>, SessionError> {
+        let environment = WonnxEnvironment::new().await;
+        Self::build(Cow::Owned(environment), model, config).await
+    }
 
+    /// Create a Session given an ONNX model, using default configuration and a private
+    /// [`WonnxEnvironment`] owned by the session.
+    pub async fn from_model(model: onnx::ModelProto) -> Result<Session<'static>, SessionError> {
+        Self::from_model_with_config(model, &SessionConfig::new()).await
+    }
+
+    /// Create a session using the provided [`onnx::ModelProto`] and [session config](SessionConfig)
+    /// that takes ownership of `environment` rather than creating a new one. Unlike
+    /// [`Session::from_model_in`], `environment` moves into the session instead of being
+    /// borrowed, so the returned session has no lifetime tied to a local; since
+    /// [`WonnxEnvironment`] is cheap to [`Clone`] (it just clones the underlying `wgpu` handles),
+    /// callers that want to share one GPU context across many long-lived sessions (e.g.
+    /// [`ModelRegistry`](crate::ModelRegistry)) can pass a clone of a shared environment here.
+    pub async fn from_model_with_environment(
+        environment: WonnxEnvironment,
+        model: onnx::ModelProto,
+        config: &SessionConfig,
+    ) -> Result<Session<'static>, SessionError> {
+        Self::build(Cow::Owned(environment), model, config).await
+    }
+
+    /// Read an ONNX model from a path and create a session that takes ownership of `environment`.
+    /// See [`Session::from_model_with_environment`].
+    pub async fn from_path_with_environment<P: AsRef<Path>>(
+        environment: WonnxEnvironment,
+        path: P,
+        config: &SessionConfig,
+    ) -> Result<Session<'static>, SessionError> {
+        let model = onnx::ModelProto::parse_from_bytes(&std::fs::read(path)?)?;
+        Self::from_model_with_environment(environment, model, config).await
+    }
+}
+
+impl<'env> Session<'env> {
+    /// Read an ONNX model from a path and create a session that borrows the given
+    /// [`WonnxEnvironment`] (and therefore its device and queue) instead of creating its own,
+    /// using the specified [session config](SessionConfig).
+    pub async fn from_path_in<P: AsRef<Path>>(
+        environment: &'env WonnxEnvironment,
+        path: P,
+        config: &SessionConfig,
+    ) -> Result<Session<'env>, SessionError> {
+        let model = onnx::ModelProto::parse_from_bytes(&std::fs::read(path)?)?;
+        Session::from_model_in(environment, model, config).await
+    }
+
+    /// Create a session using the provided [`onnx::ModelProto`] and [session config](SessionConfig)
+    /// that borrows `environment` rather than creating its own, letting several sessions share one
+    /// GPU device, queue and (eventually) pipeline cache.
+    pub async fn from_model_in(
+        environment: &'env WonnxEnvironment,
+        model: onnx::ModelProto,
+        config: &SessionConfig,
+    ) -> Result<Session<'env>, SessionError> {
+        Self::build(Cow::Borrowed(environment), model, config).await
+    }
+
+    async fn build(
+        environment: Cow<'env, WonnxEnvironment>,
+        model: onnx::ModelProto,
+        config: &SessionConfig,
+    ) -> Result<Session<'env>, SessionError> {
         // Find the version of the ONNX operator set this model is using (this is useful because some operators' specifications change over time).
         // Note, if any other op set than the ONNX operator set is referenced, we cannot run the model.
         // See https://github.com/onnx/onnx/blob/master/docs/Versioning.md#operator-sets
@@ -179,16 +294,19 @@ impl Session {
         // Optimize and compile the model graph to a set of buffers and 'builders' which can basically run GPU shader code referencing these buffers
         let onnx_opset_version = onnx_opset_version.ok_or(SessionError::UnknownOnnxOpsetVersion)?;
 
-        let mut optimizer = Optimizer::new();
+        let mut optimizer = Optimizer::new_with_level(config.optimization_level);
         let ir = optimizer.optimize(ir::Node::from_model(&model, config.outputs.as_deref())?)?;
-        let gpu_model = GpuModel::from(ir, device, queue, onnx_opset_version)?;
-
-        Ok(Session { gpu_model })
-    }
-
-    /// Create a Session given an ONNX model, using default configuration.
-    pub async fn from_model(model: onnx::ModelProto) -> Result<Session, SessionError> {
-        Self::from_model_with_config(model, &SessionConfig::new()).await
+        let gpu_model = GpuModel::from(
+            ir,
+            environment.device().clone(),
+            environment.queue().clone(),
+            onnx_opset_version,
+        )?;
+
+        Ok(Session {
+            environment,
+            gpu_model,
+        })
     }
 
     /// Perform inference given the inputs provided and return all the outputs the model was compiled to return.
@@ -198,4 +316,34 @@ impl Session {
     ) -> Result<HashMap<String, OutputTensor>, SessionError> {
         Ok(self.gpu_model.infer(inputs).await?)
     }
+
+    /// Like [`Session::run`], but also returns a per-node [`InferenceProfile`] with the GPU
+    /// wall-clock time each compiled node took. Useful for finding shader bottlenecks; has some
+    /// overhead from the timestamp queries, so prefer [`Session::run`] outside of profiling.
+    pub async fn run_with_profiling<'a>(
+        &self,
+        inputs: &HashMap<String, InputTensor<'a>>,
+    ) -> Result<(HashMap<String, OutputTensor>, InferenceProfile), SessionError> {
+        Ok(self.gpu_model.infer_with_profiling(inputs).await?)
+    }
+
+    /// The [`WonnxEnvironment`] this session is running on, whether owned or borrowed.
+    pub fn environment(&self) -> &WonnxEnvironment {
+        &self.environment
+    }
+
+    pub(crate) fn gpu_model(&self) -> &GpuModel {
+        &self.gpu_model
+    }
+
+    /// Creates a [`BoundSession`] that keeps GPU-resident input buffers and reuses them across
+    /// repeated [`BoundSession::run_bound`] calls, instead of uploading every input from host
+    /// memory on every call. No buffer is allocated here: each input buffer is allocated by the
+    /// first [`BoundSession::write_input`] for its name. Outputs are read back through the
+    /// model's own staging buffers, which both [`Session::run`] and
+    /// [`BoundSession::run_bound`] reuse. Best suited to streaming/real-time workloads that call
+    /// inference many times with identically-shaped inputs.
+    pub fn bind(&self) -> BoundSession<'_, 'env> {
+        BoundSession::new(self)
+    }
 }
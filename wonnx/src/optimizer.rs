@@ -0,0 +1,103 @@
+use crate::ir::{IrError, Node};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum OptimizerError {
+    #[error("error in intermediate representation: {0}")]
+    IrError(#[from] IrError),
+}
+
+/// Controls which graph-optimization passes [`Optimizer::optimize`] runs, mirroring ONNX
+/// Runtime's `GraphOptimizationLevel`. Levels are cumulative: each level also runs the passes
+/// of the levels below it.
+///
+/// None of the passes gated by these levels are implemented yet — [`Optimizer::optimize`]
+/// currently returns the graph unchanged regardless of level. The level-gating is wired up
+/// ahead of the passes themselves so callers and [`SessionConfig::optimization_level`](crate::SessionConfig::optimization_level)
+/// don't need to change shape once they land.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OptimizationLevel {
+    /// Run no optimization passes at all; compile the graph exactly as parsed from the model.
+    /// Useful for debugging a model that is miscompiled by one of the optimization passes, once
+    /// any exist.
+    Disabled,
+    /// Will run only cheap, always-safe passes (currently: no-op elimination). Not implemented
+    /// yet; behaves the same as [`OptimizationLevel::Disabled`].
+    Basic,
+    /// Will also run constant folding. Not implemented yet; behaves the same as
+    /// [`OptimizationLevel::Disabled`].
+    Extended,
+    /// Will run the full pass pipeline, including operator fusion. Not implemented yet; behaves
+    /// the same as [`OptimizationLevel::Disabled`]. This is the default level, so that turning
+    /// on a pass as it's implemented doesn't require callers to also raise their configured
+    /// level.
+    All,
+}
+
+impl Default for OptimizationLevel {
+    fn default() -> Self {
+        OptimizationLevel::All
+    }
+}
+
+/// Runs the configured graph-optimization passes over an IR [`Node`] tree before it is handed
+/// off to the compiler.
+pub struct Optimizer {
+    level: OptimizationLevel,
+}
+
+impl Optimizer {
+    /// Creates a new [`Optimizer`] that runs the full ([`OptimizationLevel::All`]) pass pipeline.
+    pub fn new() -> Self {
+        Self::new_with_level(OptimizationLevel::All)
+    }
+
+    /// Creates a new [`Optimizer`] that runs only the passes included in `level`.
+    pub fn new_with_level(level: OptimizationLevel) -> Self {
+        Self { level }
+    }
+
+    /// Optimizes `node` according to the configured [`OptimizationLevel`], returning the
+    /// (possibly rewritten) root of the IR tree.
+    pub fn optimize(&mut self, node: Node) -> Result<Node, OptimizerError> {
+        let mut node = node;
+
+        if self.level >= OptimizationLevel::Basic {
+            node = self.eliminate_no_ops(node)?;
+        }
+
+        if self.level >= OptimizationLevel::Extended {
+            node = self.fold_constants(node)?;
+        }
+
+        if self.level >= OptimizationLevel::All {
+            node = self.fuse_operators(node)?;
+        }
+
+        Ok(node)
+    }
+
+    /// Placeholder for a future pass that removes nodes with no effect on their output (e.g.
+    /// `Identity`, `Dropout` in inference mode). Currently a no-op.
+    fn eliminate_no_ops(&self, node: Node) -> Result<Node, OptimizerError> {
+        Ok(node)
+    }
+
+    /// Placeholder for a future pass that pre-computes the outputs of sub-graphs whose inputs
+    /// are all constant. Currently a no-op.
+    fn fold_constants(&self, node: Node) -> Result<Node, OptimizerError> {
+        Ok(node)
+    }
+
+    /// Placeholder for a future pass that merges sequences of operators with a single, more
+    /// efficient compiled form (e.g. `Conv` followed by `Relu`). Currently a no-op.
+    fn fuse_operators(&self, node: Node) -> Result<Node, OptimizerError> {
+        Ok(node)
+    }
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,107 @@
+use crate::utils::{InputTensor, OutputTensor};
+use crate::{Session, SessionConfig, SessionError, WonnxEnvironment};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RegistryError {
+    #[error("no model registered with name '{0}'")]
+    UnknownModel(String),
+
+    #[error("model '{0}' has no loaded version {1}")]
+    UnknownVersion(String, i64),
+
+    #[error("error running inference: {0}")]
+    SessionError(#[from] SessionError),
+}
+
+/// Manages several named models, each with one or more loaded versions, sharing a single GPU
+/// [`WonnxEnvironment`] across all of them. This turns wonnx from a single-model handle into the
+/// core of a deployable inference server: callers route requests to a `(name, version)` pair,
+/// `None` meaning the highest loaded version *number* (not necessarily the most recently loaded
+/// one — versions are stored in a `BTreeMap<i64, _>` ordered by number, so e.g. loading version 4
+/// after version 5 does not make 4 the one `None` resolves to), and loading or unloading a
+/// version is atomic with respect to in-flight [`ModelRegistry::infer`] calls, which keep running
+/// against the snapshot they started with.
+pub struct ModelRegistry {
+    environment: WonnxEnvironment,
+    models: RwLock<HashMap<String, BTreeMap<i64, Arc<Session<'static>>>>>,
+}
+
+impl ModelRegistry {
+    /// Creates a new, empty [`ModelRegistry`] backed by `environment`.
+    pub fn new(environment: WonnxEnvironment) -> Self {
+        Self {
+            environment,
+            models: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Compiles the model at `path` and registers it as `version` of `name`, replacing any
+    /// version already loaded under the same `(name, version)` key. In-flight
+    /// [`ModelRegistry::infer`] calls using the replaced version keep running against their
+    /// `Arc` snapshot; only new calls see the new version.
+    pub async fn load(
+        &self,
+        name: impl Into<String>,
+        version: i64,
+        path: impl AsRef<Path>,
+        config: &SessionConfig,
+    ) -> Result<(), SessionError> {
+        let session =
+            Session::from_path_with_environment(self.environment.clone(), path, config).await?;
+
+        let mut models = self.models.write().unwrap();
+        models
+            .entry(name.into())
+            .or_default()
+            .insert(version, Arc::new(session));
+        Ok(())
+    }
+
+    /// Unregisters `version` of `name`, if loaded. Returns whether a version was actually
+    /// removed. In-flight requests against it keep running; it just stops being discoverable by
+    /// [`ModelRegistry::infer`].
+    pub fn unload(&self, name: &str, version: i64) -> bool {
+        let mut models = self.models.write().unwrap();
+        let Some(versions) = models.get_mut(name) else {
+            return false;
+        };
+        let removed = versions.remove(&version).is_some();
+        if versions.is_empty() {
+            models.remove(name);
+        }
+        removed
+    }
+
+    /// Runs inference against `version` of `name` (or its highest loaded version number when
+    /// `version` is `None`).
+    pub async fn infer(
+        &self,
+        name: &str,
+        version: Option<i64>,
+        inputs: &HashMap<String, InputTensor<'_>>,
+    ) -> Result<HashMap<String, OutputTensor>, RegistryError> {
+        let session = {
+            let models = self.models.read().unwrap();
+            let versions = models
+                .get(name)
+                .ok_or_else(|| RegistryError::UnknownModel(name.to_string()))?;
+
+            let session = match version {
+                Some(version) => versions
+                    .get(&version)
+                    .ok_or_else(|| RegistryError::UnknownVersion(name.to_string(), version))?,
+                None => versions
+                    .values()
+                    .next_back()
+                    .ok_or_else(|| RegistryError::UnknownModel(name.to_string()))?,
+            };
+            Arc::clone(session)
+        };
+
+        Ok(session.run(inputs).await?)
+    }
+}
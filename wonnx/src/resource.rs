@@ -0,0 +1,121 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ResourceError {
+    #[error("no GPU adapter matched the requested device configuration; available adapters: [{0}]")]
+    NoMatchingAdapter(String),
+
+    #[error("failed to request a GPU device from the selected adapter: {0}")]
+    DeviceRequestFailed(#[from] wgpu::RequestDeviceError),
+}
+
+/// Selects which GPU adapter a [`crate::WonnxEnvironment`] runs on, for machines with more than
+/// one GPU (e.g. laptops with integrated and discrete GPUs). This is the wgpu analogue of ONNX
+/// Runtime's per-execution-provider device selection.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct DeviceConfig {
+    /// Whether to prefer a low-power (integrated) or high-performance (discrete) adapter.
+    /// Defaults to [`wgpu::PowerPreference::default`].
+    pub power_preference: wgpu::PowerPreference,
+
+    /// When set, only adapters whose name contains this substring (case-insensitive) are
+    /// considered.
+    pub adapter_name_filter: Option<String>,
+
+    /// When set, restricts adapter enumeration to the given backend(s) (e.g. Vulkan, Metal,
+    /// DX12). Defaults to [`wgpu::Backends::PRIMARY`].
+    pub backends: Option<wgpu::Backends>,
+}
+
+impl DeviceConfig {
+    /// Creates a new [`DeviceConfig`] with the default options set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets [`DeviceConfig::power_preference`] and returns [Self].
+    pub fn with_power_preference(mut self, power_preference: wgpu::PowerPreference) -> Self {
+        self.power_preference = power_preference;
+        self
+    }
+
+    /// Sets [`DeviceConfig::adapter_name_filter`] and returns [Self].
+    pub fn with_adapter_name_filter(mut self, filter: impl Into<String>) -> Self {
+        self.adapter_name_filter = Some(filter.into());
+        self
+    }
+
+    /// Sets [`DeviceConfig::backends`] and returns [Self].
+    pub fn with_backends(mut self, backends: wgpu::Backends) -> Self {
+        self.backends = Some(backends);
+        self
+    }
+
+    fn backends(&self) -> wgpu::Backends {
+        self.backends.unwrap_or(wgpu::Backends::PRIMARY)
+    }
+}
+
+/// Requests a GPU device, queue and adapter using the default [`DeviceConfig`], panicking if
+/// none is available. This is the convenience path used by [`crate::Session::from_model`] and
+/// friends.
+pub async fn request_device_queue() -> (wgpu::Device, wgpu::Queue, wgpu::Adapter) {
+    request_device_queue_with_config(&DeviceConfig::default())
+        .await
+        .expect("no suitable GPU adapter found; pass a DeviceConfig to diagnose which adapters were enumerated")
+}
+
+/// Requests a GPU device, queue and the adapter they were created from, matching `config` and
+/// returning a [`ResourceError`] listing the enumerated adapters if none match. The adapter is
+/// returned (not just the device/queue) because [`crate::WonnxEnvironment`] keeps it around,
+/// e.g. to check adapter features before requesting optional ones.
+pub async fn request_device_queue_with_config(
+    config: &DeviceConfig,
+) -> Result<(wgpu::Device, wgpu::Queue, wgpu::Adapter), ResourceError> {
+    let instance = wgpu::Instance::new(config.backends());
+
+    let adapter = match &config.adapter_name_filter {
+        // A name filter requires enumerating every adapter ourselves; `request_adapter` has no
+        // way to express "any adapter whose name contains X".
+        Some(filter) => {
+            let candidates: Vec<wgpu::Adapter> =
+                instance.enumerate_adapters(config.backends()).collect();
+            let adapter_names: Vec<String> = candidates
+                .iter()
+                .map(|adapter| adapter.get_info().name)
+                .collect();
+            let filter = filter.to_lowercase();
+
+            candidates
+                .into_iter()
+                .find(|adapter| adapter.get_info().name.to_lowercase().contains(&filter))
+                .ok_or_else(|| ResourceError::NoMatchingAdapter(adapter_names.join(", ")))?
+        }
+        None => instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: config.power_preference,
+                compatible_surface: None,
+                force_fallback_adapter: false,
+            })
+            .await
+            .ok_or_else(|| ResourceError::NoMatchingAdapter(String::new()))?,
+    };
+
+    // Request TIMESTAMP_QUERY when the adapter supports it, so `GpuModel::infer_with_profiling`
+    // can actually use it; requesting an unsupported feature would make `request_device` fail
+    // outright, so this has to be conditional rather than always requested.
+    let optional_features = wgpu::Features::TIMESTAMP_QUERY & adapter.features();
+
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                features: optional_features,
+                ..Default::default()
+            },
+            None,
+        )
+        .await?;
+
+    Ok((device, queue, adapter))
+}
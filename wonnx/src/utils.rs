@@ -0,0 +1,53 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum DataTypeError {
+    #[error("unsupported data type: {0}")]
+    UnsupportedDataType(String),
+
+    #[error("expected a tensor of {0} elements, got {1}")]
+    InvalidElementCount(usize, usize),
+}
+
+/// An input tensor passed to [`crate::Session::run`]. Borrows its data rather than copying it,
+/// since the caller typically already owns a buffer of the right shape.
+#[derive(Debug, Clone)]
+pub enum InputTensor<'a> {
+    F32(std::borrow::Cow<'a, [f32]>),
+    I32(std::borrow::Cow<'a, [i32]>),
+    I64(std::borrow::Cow<'a, [i64]>),
+}
+
+impl<'a> InputTensor<'a> {
+    pub fn as_slice(&self) -> Result<&[f32], DataTypeError> {
+        match self {
+            InputTensor::F32(data) => Ok(data),
+            other => Err(DataTypeError::UnsupportedDataType(format!("{other:?}"))),
+        }
+    }
+}
+
+impl<'a> From<&'a [f32]> for InputTensor<'a> {
+    fn from(data: &'a [f32]) -> Self {
+        InputTensor::F32(std::borrow::Cow::Borrowed(data))
+    }
+}
+
+/// The key side of a classifier `map<string/int64, float>` output, i.e. a class label.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapKey {
+    Int64(i64),
+    String(String),
+}
+
+/// An output produced by [`crate::Session::run`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutputTensor {
+    F32(Vec<f32>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    /// A classifier label -> probability map, as produced by a `ZipMap` node.
+    Map(Vec<(MapKey, f32)>),
+    /// A string tensor.
+    Strings(Vec<String>),
+}